@@ -0,0 +1,58 @@
+use crate::{CollateError, Collator};
+use std::io::Read;
+use std::path::Path;
+
+/// Returns true when `path` looks like a tar (optionally gzipped) archive,
+/// based on its file extension.
+pub fn is_archive_path<P: AsRef<Path>>(path: P) -> bool {
+        let name = match path.as_ref().file_name().and_then(|n| n.to_str()) {
+                Some(n) => n,
+                None => return false,
+        };
+
+        name.ends_with(".tar") || name.ends_with(".tar.gz")
+}
+
+/// Fold every regular-file entry of a `.tar` or `.tar.gz` archive into the
+/// collated set, running each entry's contents through the same per-line
+/// parsing [`Collator::ingest_line`] uses for loose files.
+pub fn ingest_archive_path<P: AsRef<Path>>(collator: &mut Collator, path: P, quiet: bool) -> Result<(), CollateError> {
+        let path = path.as_ref();
+        let f = std::fs::File::open(path)?;
+
+        if path.to_str().is_some_and(|s| s.ends_with(".gz")) {
+                ingest_archive_reader(collator, flate2::read::GzDecoder::new(f), quiet)
+        } else {
+                ingest_archive_reader(collator, f, quiet)
+        }
+}
+
+fn ingest_archive_reader<R: Read>(collator: &mut Collator, reader: R, quiet: bool) -> Result<(), CollateError> {
+        let mut archive = tar::Archive::new(reader);
+
+        for entry in archive.entries()? {
+                let mut entry = match entry {
+                        Ok(e) => e,
+                        Err(e) => {
+                                if !quiet { println!("Failed reading archive entry: {}", e); }
+                                continue;
+                        }
+                };
+
+                if !entry.header().entry_type().is_file() {
+                        continue;
+                }
+
+                let mut contents = String::new();
+                if let Err(e) = entry.read_to_string(&mut contents) {
+                        if !quiet { println!("Failed reading archive entry: {}", e); }
+                        continue;
+                }
+
+                for line in contents.split('\n') {
+                        collator.ingest_line(line, quiet);
+                }
+        }
+
+        Ok(())
+}
@@ -0,0 +1,210 @@
+use crate::{CollateError, RecType, SatRec};
+use clap::ValueEnum;
+use std::f64::consts::PI;
+use std::fmt;
+
+/// Text format to render collated records in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default)]
+pub enum OutputFormat {
+        /// Echo each record's original NMEA sentence verbatim (the default).
+        #[default]
+        Nmea,
+        /// YUMA almanac text format, understood by most GNSS planning tools.
+        Yuma,
+        /// SEM almanac text format.
+        Sem,
+}
+
+impl fmt::Display for OutputFormat {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let name = match self {
+                        OutputFormat::Nmea => "nmea",
+                        OutputFormat::Yuma => "yuma",
+                        OutputFormat::Sem => "sem",
+                };
+                write!(f, "{}", name)
+        }
+}
+
+/// The GPS orbital elements packed into [`SatRec::rem`] for a GPS almanac
+/// record, decoded from their STMicro payload layout and scaled by LSB into
+/// the units YUMA/SEM expect.
+struct GpsOrbit {
+        eccentricity: f64,
+        delta_i: f64,
+        omega_dot: f64,
+        health: u8,
+        sqrt_a: f64,
+        omega0: f64,
+        omega: f64,
+        m0: f64,
+        af0: f64,
+        af1: f64,
+}
+
+// Byte offsets and LSBs below follow the byte-aligned STMicro almanac
+// payload: a 2-byte eccentricity, 2-byte inclination offset from the 0.3*pi
+// nominal, 2-byte rate of right ascension, a health byte, 4-byte sqrt(A),
+// and three 4-byte signed angles, followed by two 2-byte clock terms.
+const GPS_ORBIT_LEN: usize = 27;
+
+/// IS-GPS-200 nominal inclination (i0), in semicircles. `GpsOrbit::delta_i`
+/// is an offset from this value, not the inclination itself.
+const GPS_NOMINAL_INCLINATION: f64 = 0.30;
+
+fn decode_gps_orbit(rem: &[u8]) -> Result<GpsOrbit, CollateError> {
+        if rem.len() < GPS_ORBIT_LEN {
+                return Err(CollateError::ShortRecord { expected: GPS_ORBIT_LEN, got: rem.len() });
+        }
+
+        let read_u16 = |o: usize| u16::from_le_bytes([rem[o], rem[o + 1]]);
+        let read_i16 = |o: usize| i16::from_le_bytes([rem[o], rem[o + 1]]);
+        let read_i32 = |o: usize| i32::from_le_bytes([rem[o], rem[o + 1], rem[o + 2], rem[o + 3]]);
+        let read_u32 = |o: usize| u32::from_le_bytes([rem[o], rem[o + 1], rem[o + 2], rem[o + 3]]);
+
+        Ok(GpsOrbit {
+                eccentricity: f64::from(read_u16(0)) * 2f64.powi(-21),
+                delta_i: f64::from(read_i16(2)) * 2f64.powi(-19),
+                omega_dot: f64::from(read_i16(4)) * 2f64.powi(-38),
+                health: rem[6],
+                sqrt_a: f64::from(read_u32(7)) * 2f64.powi(-11),
+                omega0: f64::from(read_i32(11)) * 2f64.powi(-23),
+                omega: f64::from(read_i32(15)) * 2f64.powi(-23),
+                m0: f64::from(read_i32(19)) * 2f64.powi(-23),
+                af0: f64::from(read_i16(23)) * 2f64.powi(-20),
+                af1: f64::from(read_i16(25)) * 2f64.powi(-38),
+        })
+}
+
+fn is_gps_almanac(sat: &SatRec) -> bool {
+        sat.rec_type == RecType::Almanac && sat.satid > 0 && sat.satid <= 32
+}
+
+/// Render collated records in the given [`OutputFormat`].
+pub fn render(records: &[&SatRec], format: OutputFormat) -> Result<String, CollateError> {
+        match format {
+                OutputFormat::Nmea => Ok(render_nmea(records)),
+                OutputFormat::Yuma => render_yuma(records),
+                OutputFormat::Sem => render_sem(records),
+        }
+}
+
+fn render_nmea(records: &[&SatRec]) -> String {
+        let mut out = String::new();
+
+        for sat in records {
+                out.push_str(&sat.nmea);
+                out.push('\n');
+        }
+
+        out
+}
+
+fn render_yuma(records: &[&SatRec]) -> Result<String, CollateError> {
+        let mut out = String::new();
+
+        for sat in records {
+                if !is_gps_almanac(sat) {
+                        continue;
+                }
+
+                let orbit = decode_gps_orbit(&sat.rem)?;
+
+                out.push_str(&format!("******** Week {} almanac for PRN-{:02} ********\n", sat.week, sat.satid));
+                out.push_str(&format!("ID:                         {:02}\n", sat.satid));
+                out.push_str(&format!("Health:                     {:03}\n", orbit.health));
+                out.push_str(&format!("Eccentricity:                {:.10E}\n", orbit.eccentricity));
+                out.push_str(&format!("Time of Applicability(s):  {:.4}\n", f64::from(sat.toa) * 4096.0));
+                out.push_str(&format!("Orbital Inclination(rad):   {:.10}\n", (GPS_NOMINAL_INCLINATION + orbit.delta_i) * PI));
+                out.push_str(&format!("Rate of Right Ascen(r/s):  {:.10E}\n", orbit.omega_dot * PI));
+                out.push_str(&format!("SQRT(A)  (m 1/2):           {:.10}\n", orbit.sqrt_a));
+                out.push_str(&format!("Right Ascen at Week(rad):   {:.10}\n", orbit.omega0 * PI));
+                out.push_str(&format!("Argument of Perigee(rad):   {:.10}\n", orbit.omega * PI));
+                out.push_str(&format!("Mean Anom(rad):              {:.10}\n", orbit.m0 * PI));
+                out.push_str(&format!("Af0(s):                      {:.10E}\n", orbit.af0));
+                out.push_str(&format!("Af1(s/s):                    {:.10E}\n", orbit.af1));
+                out.push_str(&format!("week:                        {}\n", sat.week));
+                out.push('\n');
+        }
+
+        Ok(out)
+}
+
+fn render_sem(records: &[&SatRec]) -> Result<String, CollateError> {
+        let gps: Vec<&&SatRec> = records.iter().filter(|sat| is_gps_almanac(sat)).collect();
+
+        let mut out = String::new();
+        out.push_str(&format!("{}\n", gps.len()));
+
+        let week = gps.first().map_or(0, |sat| sat.week);
+        let toa_seconds = gps.first().map_or(0.0, |sat| f64::from(sat.toa) * 4096.0);
+        out.push_str(&format!("{} {:.1}\n", week, toa_seconds));
+        out.push('\n');
+
+        for sat in gps {
+                let orbit = decode_gps_orbit(&sat.rem)?;
+
+                out.push_str(&format!("{}\n", sat.satid));
+                out.push_str(&format!("{}\n", sat.satid));
+                out.push_str("0\n");
+                out.push_str(&format!("{:.10E}\n", orbit.eccentricity));
+                out.push_str(&format!("{:.10}\n", (GPS_NOMINAL_INCLINATION + orbit.delta_i) * PI));
+                out.push_str(&format!("{:.10E}\n", orbit.omega_dot * PI));
+                out.push_str(&format!("{:.10}\n", orbit.sqrt_a));
+                out.push_str(&format!("{:.10}\n", orbit.omega0 * PI));
+                out.push_str(&format!("{:.10}\n", orbit.omega * PI));
+                out.push_str(&format!("{:.10}\n", orbit.m0 * PI));
+                out.push_str(&format!("{:.10E}\n", orbit.af0));
+                out.push_str(&format!("{:.10E}\n", orbit.af1));
+                out.push_str(&format!("{}\n", sat.week));
+                out.push_str(&format!("{}\n", orbit.health));
+                out.push_str("0\n");
+                out.push('\n');
+        }
+
+        Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        fn gps_almanac(satid: u16, week: u16, toa: u8, rem: Vec<u8>) -> SatRec {
+                SatRec { satid, week, toa, rec_type: RecType::Almanac, rem, ..Default::default() }
+        }
+
+        #[test]
+        fn decode_gps_orbit_reads_le_fields_at_their_offsets() {
+                let mut rem = vec![0u8; GPS_ORBIT_LEN];
+                rem[0..2].copy_from_slice(&1000u16.to_le_bytes());
+                rem[2..4].copy_from_slice(&5243i16.to_le_bytes());
+                rem[6] = 7;
+
+                let orbit = decode_gps_orbit(&rem).expect("should decode");
+                assert_eq!(orbit.eccentricity, 1000.0 * 2f64.powi(-21));
+                assert_eq!(orbit.delta_i, 5243.0 * 2f64.powi(-19));
+                assert_eq!(orbit.health, 7);
+        }
+
+        #[test]
+        fn decode_gps_orbit_reports_short_record() {
+                assert!(matches!(decode_gps_orbit(&[0u8; 4]), Err(CollateError::ShortRecord { .. })));
+        }
+
+        #[test]
+        fn render_yuma_adds_nominal_inclination_to_the_payload_offset() {
+                let mut rem = vec![0u8; GPS_ORBIT_LEN];
+                rem[2..4].copy_from_slice(&5243i16.to_le_bytes());
+
+                let sat = gps_almanac(5, 100, 10, rem);
+                let rendered = render_yuma(&[&sat]).expect("should render");
+
+                let delta_i = 5243.0 * 2f64.powi(-19);
+                let expected = (GPS_NOMINAL_INCLINATION + delta_i) * PI;
+                let line = rendered.lines().find(|l| l.starts_with("Orbital Inclination(rad):")).unwrap();
+                let got: f64 = line.rsplit(' ').next().unwrap().parse().unwrap();
+
+                assert!((got - expected).abs() < 1e-9, "got {}, expected {}", got, expected);
+                assert!(expected > 0.9, "inclination should be near the ~0.3*pi nominal, not near zero: {}", expected);
+        }
+}
@@ -0,0 +1,713 @@
+// The fill_* functions build each record by constructing a Default and then
+// assigning fields one at a time, and satid ranges are spelled out as
+// `>= lo && <= hi` rather than RangeInclusive::contains — both match this
+// crate's established style for the STMicro payload decoders, so they're
+// allowed here rather than rewritten wholesale.
+#![allow(clippy::field_reassign_with_default, clippy::manual_range_contains, clippy::needless_return, clippy::needless_late_init)]
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read};
+
+mod archive;
+pub use archive::{ingest_archive_path, is_archive_path};
+
+mod format;
+pub use format::{render, OutputFormat};
+
+/// The kind of record a PSTM sentence carries.
+#[derive(Debug, PartialEq, Default)]
+pub enum RecType {
+        #[default]
+        Undefined,
+        Almanac,
+        Ephemeris,
+}
+
+/// A collated per-satellite record: the freshest almanac or ephemeris seen
+/// for a given `satid`, along with the original NMEA sentence it came from.
+#[derive(Debug, Default)]
+pub struct SatRec {
+        pub satid: u16,
+        pub week: u16,
+        pub toa: u8,
+        pub toe: u32,
+        pub toc: u16,
+        pub rec_type: RecType,
+        pub nmea: String,
+        /// Undecoded almanac/ephemeris payload bytes, kept around so output
+        /// formats that need more than `week`/`toa`/`toe`/`toc` (YUMA, SEM)
+        /// can decode the orbital parameters themselves.
+        pub rem: Vec<u8>,
+}
+
+/// Everything that can go wrong turning a PSTM sentence into a [`SatRec`].
+#[derive(Debug)]
+pub enum CollateError {
+        MalformedLine,
+        UnknownRecord,
+        BadChecksum { computed: u8, expected: u8 },
+        Hex(hex::FromHexError),
+        Io(io::Error),
+        ShortRecord { expected: usize, got: usize },
+}
+
+impl fmt::Display for CollateError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                        CollateError::MalformedLine => write!(f, "malformed line"),
+                        CollateError::UnknownRecord => write!(f, "unknown record type"),
+                        CollateError::BadChecksum { computed, expected } => {
+                                write!(f, "bad checksum: computed {:#04x}, expected {:#04x}", computed, expected)
+                        }
+                        CollateError::Hex(e) => write!(f, "failed to decode hex record: {}", e),
+                        CollateError::Io(e) => write!(f, "i/o error: {}", e),
+                        CollateError::ShortRecord { expected, got } => {
+                                write!(f, "record too short: expected at least {} bytes, got {}", expected, got)
+                        }
+                }
+        }
+}
+
+impl std::error::Error for CollateError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                match self {
+                        CollateError::Hex(e) => Some(e),
+                        CollateError::Io(e) => Some(e),
+                        _ => None,
+                }
+        }
+}
+
+impl From<hex::FromHexError> for CollateError {
+        fn from(e: hex::FromHexError) -> Self {
+                CollateError::Hex(e)
+        }
+}
+
+impl From<io::Error> for CollateError {
+        fn from(e: io::Error) -> Self {
+                CollateError::Io(e)
+        }
+}
+
+fn require_len(bytes: &[u8], expected: usize) -> Result<(), CollateError> {
+        if bytes.len() < expected {
+                Err(CollateError::ShortRecord { expected, got: bytes.len() })
+        } else {
+                Ok(())
+        }
+}
+
+#[derive(Debug, Default)]
+struct  GpsAlmanac{
+        satid: u8,
+        week: u16,
+        toa: u8,
+        rem: Vec<u8>,
+}
+
+fn fill_gps_almanac( bytes: Vec<u8> ) -> Result<GpsAlmanac, CollateError> {
+        require_len(&bytes, 4)?;
+
+        let mut data: GpsAlmanac = Default::default();
+
+        data.satid = bytes[0];
+        data.week = (u16::from(bytes[2])).checked_shl(8).unwrap_or(0) + u16::from(bytes[1]);
+        data.toa = bytes[3];
+        data.rem = bytes[4..].to_vec();
+
+        Ok(data)
+}
+
+#[derive(Debug, Default)]
+struct GpsEphemeris {
+        week: u16,
+        toe: u16,
+        toc: u16,
+        rem: Vec<u8>,
+}
+
+fn fill_gps_ephemeris( bytes: Vec<u8> ) -> Result<GpsEphemeris, CollateError> {
+        require_len(&bytes, 6)?;
+
+        let mut data: GpsEphemeris = Default::default();
+
+        data.week = (u16::from(bytes[1])).checked_shl(8).unwrap_or(0) + u16::from(bytes[0]);
+        data.toe = (u16::from(bytes[3])).checked_shl(8).unwrap_or(0) + u16::from(bytes[2]);
+        data.toc = (u16::from(bytes[5])).checked_shl(8).unwrap_or(0) + u16::from(bytes[4]);
+        data.rem = bytes[5..].to_vec();
+
+        Ok(data)
+}
+
+#[derive(Debug, Default)]
+struct GlonassAlmanac{
+        satid: u8,
+        week: u16,
+        toa: u8,
+        rem: Vec<u8>,
+}
+
+fn fill_glonass_alamanc( bytes: Vec<u8> ) -> Result<GlonassAlmanac, CollateError> {
+        require_len(&bytes, 4)?;
+
+        let mut data: GlonassAlmanac = Default::default();
+
+        data.satid = bytes[0];
+        data.week = (u16::from(bytes[2])).checked_shl(8).unwrap_or(0) + u16::from(bytes[1]);
+        data.toa = bytes[3];
+        data.rem = bytes[4..].to_vec();
+
+        Ok(data)
+}
+
+#[derive(Debug, Default)]
+struct GlonassEphemeris{
+        week: u16,
+        toe: u32,
+        rem: Vec<u8>,
+}
+
+fn fill_glonass_ephemeris( bytes: Vec<u8> ) -> Result<GlonassEphemeris, CollateError> {
+        require_len(&bytes, 5)?;
+
+        let mut data: GlonassEphemeris = Default::default();
+
+        data.week = (u16::from(bytes[1])).checked_shl(8).unwrap_or(0) + u16::from(bytes[0]);
+        data.toe = (u32::from(bytes[3])).checked_shl(12).unwrap_or(0) + (u32::from(bytes[2])).checked_shl(4).unwrap_or(0) + u32::from(bytes[4]);
+        data.rem = bytes[5..].to_vec();
+
+        Ok(data)
+}
+
+#[derive(Debug, Default)]
+struct GalileoAlmanac {
+        satid: u16,
+        svid: u8,
+        week: u16,
+        toa: u8,
+        rem: Vec<u8>,
+}
+
+fn fill_galileo_almanac( bytes: Vec<u8> ) -> Result<GalileoAlmanac, CollateError> {
+        require_len(&bytes, 6)?;
+
+        let mut data: GalileoAlmanac = Default::default();
+
+        data.satid = (u16::from(bytes[1])).checked_shl(8).unwrap_or(0) + u16::from(bytes[0]);
+        data.svid = bytes[2];
+        data.week = (u16::from(bytes[4])).checked_shl(8).unwrap_or(0) + u16::from(bytes[3]);
+        data.toa = bytes[5];
+        data.rem = bytes[6..].to_vec();
+
+        Ok(data)
+}
+
+#[derive(Debug, Default)]
+struct SbasAlmanac {
+        satid: u8,
+        week: u16,
+        toa: u8,
+        rem: Vec<u8>,
+}
+
+fn fill_sbas_almanac( bytes: Vec<u8> ) -> Result<SbasAlmanac, CollateError> {
+        require_len(&bytes, 4)?;
+
+        let mut data: SbasAlmanac = Default::default();
+
+        data.satid = bytes[0];
+        data.week = (u16::from(bytes[2])).checked_shl(8).unwrap_or(0) + u16::from(bytes[1]);
+        data.toa = bytes[3];
+        data.rem = bytes[4..].to_vec();
+
+        Ok(data)
+}
+
+#[derive(Debug, Default)]
+struct SbasEphemeris {
+        week: u16,
+        toe: u16,
+        toc: u16,
+        rem: Vec<u8>,
+}
+
+fn fill_sbas_ephemeris( bytes: Vec<u8> ) -> Result<SbasEphemeris, CollateError> {
+        require_len(&bytes, 6)?;
+
+        let mut data: SbasEphemeris = Default::default();
+
+        data.week = (u16::from(bytes[1])).checked_shl(8).unwrap_or(0) + u16::from(bytes[0]);
+        data.toe = (u16::from(bytes[3])).checked_shl(8).unwrap_or(0) + u16::from(bytes[2]);
+        data.toc = (u16::from(bytes[5])).checked_shl(8).unwrap_or(0) + u16::from(bytes[4]);
+        data.rem = bytes[6..].to_vec();
+
+        Ok(data)
+}
+
+#[derive(Debug, Default)]
+struct QzssAlmanac {
+        satid: u8,
+        week: u16,
+        toa: u8,
+        rem: Vec<u8>,
+}
+
+fn fill_qzss_almanac( bytes: Vec<u8> ) -> Result<QzssAlmanac, CollateError> {
+        require_len(&bytes, 4)?;
+
+        let mut data: QzssAlmanac = Default::default();
+
+        data.satid = bytes[0];
+        data.week = (u16::from(bytes[2])).checked_shl(8).unwrap_or(0) + u16::from(bytes[1]);
+        data.toa = bytes[3];
+        data.rem = bytes[4..].to_vec();
+
+        Ok(data)
+}
+
+#[derive(Debug, Default)]
+struct QzssEphemeris {
+        week: u16,
+        toe: u16,
+        toc: u16,
+        rem: Vec<u8>,
+}
+
+fn fill_qzss_ephemeris( bytes: Vec<u8> ) -> Result<QzssEphemeris, CollateError> {
+        require_len(&bytes, 6)?;
+
+        let mut data: QzssEphemeris = Default::default();
+
+        data.week = (u16::from(bytes[1])).checked_shl(8).unwrap_or(0) + u16::from(bytes[0]);
+        data.toe = (u16::from(bytes[3])).checked_shl(8).unwrap_or(0) + u16::from(bytes[2]);
+        data.toc = (u16::from(bytes[5])).checked_shl(8).unwrap_or(0) + u16::from(bytes[4]);
+        data.rem = bytes[6..].to_vec();
+
+        Ok(data)
+}
+
+#[derive(Debug, Default)]
+struct BeidouAlmanac {
+        satid: u16,
+        week: u16,
+        toa: u8,
+        rem: Vec<u8>,
+}
+
+fn fill_beidou_almanac( bytes: Vec<u8> ) -> Result<BeidouAlmanac, CollateError> {
+        require_len(&bytes, 5)?;
+
+        let mut data: BeidouAlmanac = Default::default();
+
+        data.satid = (u16::from(bytes[1])).checked_shl(8).unwrap_or(0) + u16::from(bytes[0]);
+        data.week = (u16::from(bytes[3])).checked_shl(8).unwrap_or(0) + u16::from(bytes[2]);
+        data.toa = bytes[4];
+        data.rem = bytes[5..].to_vec();
+
+        Ok(data)
+}
+
+#[derive(Debug, Default)]
+struct BeidouEphemeris {
+        week: u16,
+        toe: u16,
+        toc: u16,
+        rem: Vec<u8>,
+}
+
+fn fill_beidou_ephemeris( bytes: Vec<u8> ) -> Result<BeidouEphemeris, CollateError> {
+        require_len(&bytes, 6)?;
+
+        let mut data: BeidouEphemeris = Default::default();
+
+        data.week = (u16::from(bytes[1])).checked_shl(8).unwrap_or(0) + u16::from(bytes[0]);
+        data.toe = (u16::from(bytes[3])).checked_shl(8).unwrap_or(0) + u16::from(bytes[2]);
+        data.toc = (u16::from(bytes[5])).checked_shl(8).unwrap_or(0) + u16::from(bytes[4]);
+        data.rem = bytes[6..].to_vec();
+
+        Ok(data)
+}
+
+fn check_checksum( chars: &str, sum: u8 ) -> Result<u8, CollateError> {
+        let mut checksum: u8 = 0;
+
+        for c in chars.bytes() {
+                checksum ^= c;
+        }
+
+        if checksum == sum {
+                Ok(sum)
+        } else {
+                Err(CollateError::BadChecksum { computed: checksum, expected: sum })
+        }
+}
+
+/// Parse one PSTM almanac/ephemeris NMEA sentence into a [`SatRec`].
+///
+/// Unlike the record's own satellite system, `parse_line` never panics on
+/// malformed input: truncated records, bad hex, and checksum mismatches are
+/// reported as a [`CollateError`] instead.
+pub fn parse_line( line: &str, quiet: bool ) -> Result<SatRec, CollateError> {
+        let fields = line.split(",");
+        let field: Vec<&str> = fields.collect();
+        let satid: u16;
+        let len: i32;
+        let mut sat: SatRec = Default::default();
+
+        if field.len() != 4 {
+                return Err(CollateError::MalformedLine);
+        }
+
+        if field[0].eq("$PSTMALMANAC") {
+                if !quiet { println!("reading almanac record\n"); }
+                sat.rec_type = RecType::Almanac;
+        } else if field[0].eq("$PSTMEPHEM") {
+                if !quiet { println!("reading ephemeris record\n"); }
+                sat.rec_type = RecType::Ephemeris;
+        } else {
+                return Err(CollateError::UnknownRecord);
+        }
+
+        match field[1].parse::<u16>() {
+                Ok(n) => satid = n,
+                Err(_) => return Err(CollateError::MalformedLine),
+        }
+
+        match field[2].parse::<i32>() {
+                Ok(n) => len = n,
+                Err(_) => return Err(CollateError::MalformedLine),
+        }
+
+        let record: Vec<&str> = field[3].split("*").collect();
+        if record.len() != 2 {
+                return Err(CollateError::MalformedLine);
+        }
+
+        let sum = hex::decode(record[1])?;
+        let bytes = hex::decode(record[0])?;
+        if sum.is_empty() {
+                return Err(CollateError::MalformedLine);
+        }
+
+        let chars: Vec<&str> = line.split("*").collect();
+        if chars.is_empty() || chars[0].is_empty() {
+                return Err(CollateError::MalformedLine);
+        }
+
+        check_checksum( &chars[0][1..], sum[0] )?;
+
+        if sat.rec_type == RecType::Almanac {
+                if len != 40 {
+                        return Err(CollateError::MalformedLine);
+                }
+
+                if satid <= 32 {
+                        let gps_rec = fill_gps_almanac(bytes)?;
+                        if !quiet { println!("gps {:?}", gps_rec); }
+                        sat.satid = gps_rec.satid as u16;
+                        sat.week = gps_rec.week;
+                        sat.toa = gps_rec.toa;
+                        sat.rem = gps_rec.rem;
+                } else if satid >= 33 && satid <= 96 {
+                        let glonass_rec = fill_glonass_alamanc(bytes)?;
+                        if !quiet { println!("glonass {} {:?}", satid, glonass_rec); }
+                        sat.satid = glonass_rec.satid as u16;
+                        sat.week = glonass_rec.week;
+                        sat.toa = glonass_rec.toa;
+                } else if satid >= 301 && satid <= 336 {
+                        let galileo_rec = fill_galileo_almanac(bytes)?;
+                        if !quiet { println!("galileo {} {:?}", satid, galileo_rec); }
+                        sat.satid = galileo_rec.satid;
+                        sat.week = galileo_rec.week;
+                        sat.toa = galileo_rec.toa;
+                } else if satid >= 120 && satid <= 158 {
+                        let sbas_rec = fill_sbas_almanac(bytes)?;
+                        if !quiet { println!("sbas {} {:?}", satid, sbas_rec); }
+                        sat.satid = sbas_rec.satid as u16;
+                        sat.week = sbas_rec.week;
+                        sat.toa = sbas_rec.toa;
+                } else if satid >= 193 && satid <= 197 {
+                        let qzss_rec = fill_qzss_almanac(bytes)?;
+                        if !quiet { println!("qzss {} {:?}", satid, qzss_rec); }
+                        sat.satid = qzss_rec.satid as u16;
+                        sat.week = qzss_rec.week;
+                        sat.toa = qzss_rec.toa;
+                } else if satid >= 401 && satid <= 437 {
+                        let beidou_rec = fill_beidou_almanac(bytes)?;
+                        if !quiet { println!("beidou {} {:?}", satid, beidou_rec); }
+                        sat.satid = beidou_rec.satid;
+                        sat.week = beidou_rec.week;
+                        sat.toa = beidou_rec.toa;
+                } else {
+                        eprintln!("warning: unsupported satellite system for satid {}", satid);
+                }
+        } else if sat.rec_type == RecType::Ephemeris {
+                if len != 64 {
+                        return Err(CollateError::MalformedLine);
+                }
+
+                if satid <= 32 {
+                        let gps_rec = fill_gps_ephemeris(bytes)?;
+                        if !quiet { println!("gps ephemeris {:?}", gps_rec); }
+                        sat.satid = satid;
+                        sat.week = gps_rec.week;
+                        sat.toc = gps_rec.toc;
+                        sat.toe = gps_rec.toe as u32;
+                } else if satid >= 33 && satid <= 96 {
+                        let glonass_rec = fill_glonass_ephemeris(bytes)?;
+                        if !quiet { println!("glonass {} {:?}", satid, glonass_rec); }
+                        sat.satid = satid;
+                        sat.week = glonass_rec.week;
+                        sat.toe = glonass_rec.toe;
+                } else if satid >= 120 && satid <= 158 {
+                        let sbas_rec = fill_sbas_ephemeris(bytes)?;
+                        if !quiet { println!("sbas ephemeris {} {:?}", satid, sbas_rec); }
+                        sat.satid = satid;
+                        sat.week = sbas_rec.week;
+                        sat.toc = sbas_rec.toc;
+                        sat.toe = sbas_rec.toe as u32;
+                } else if satid >= 193 && satid <= 197 {
+                        let qzss_rec = fill_qzss_ephemeris(bytes)?;
+                        if !quiet { println!("qzss ephemeris {} {:?}", satid, qzss_rec); }
+                        sat.satid = satid;
+                        sat.week = qzss_rec.week;
+                        sat.toc = qzss_rec.toc;
+                        sat.toe = qzss_rec.toe as u32;
+                } else if satid >= 401 && satid <= 437 {
+                        let beidou_rec = fill_beidou_ephemeris(bytes)?;
+                        if !quiet { println!("beidou ephemeris {} {:?}", satid, beidou_rec); }
+                        sat.satid = satid;
+                        sat.week = beidou_rec.week;
+                        sat.toc = beidou_rec.toc;
+                        sat.toe = beidou_rec.toe as u32;
+                } else {
+                        eprintln!("warning: unsupported satellite system for satid {}", satid);
+                }
+        }
+
+        sat.nmea = line.to_string();
+
+        Ok(sat)
+}
+
+fn compare_date( rec1: &SatRec, rec2: &SatRec ) -> Result<i8, String> {
+        if rec1.rec_type != rec2.rec_type {
+                return Err("can't compare different types".to_string());
+        }
+
+        if rec1.rec_type == RecType::Almanac {
+                return compare_almanac_date(rec1, rec2);
+        }
+
+        if rec1.rec_type == RecType::Ephemeris {
+                return compare_ephemeris_date(rec1, rec2);
+        }
+
+        return Err("Invalid date type".to_string());
+}
+
+fn compare_almanac_date( rec1: &SatRec, rec2: &SatRec ) -> Result<i8, String> {
+        if rec1.rec_type != rec2.rec_type {
+                return Err("can't compare different types".to_string());
+        }
+
+        if rec1.week > rec2.week {
+                return Ok(1);
+        }
+
+        if rec1.week < rec2.week {
+                return Ok(-1);
+        }
+
+        if rec1.toa > rec2.toa {
+                return Ok(2);
+        }
+
+        if rec1.toa < rec2.toa {
+                return Ok(-2);
+        }
+
+        return Ok(0);
+}
+
+fn compare_ephemeris_date( rec1: &SatRec, rec2: &SatRec ) -> Result<i8, String> {
+        if rec1.rec_type != rec2.rec_type {
+                return Err("can't compare different types".to_string());
+        }
+
+        if rec1.week > rec2.week {
+                return Ok(1);
+        }
+
+        if rec1.week < rec2.week {
+                return Ok(-1);
+        }
+
+        if rec1.toe > rec2.toe {
+                return Ok(2);
+        }
+
+        if rec1.toe < rec2.toe {
+                return Ok(-2);
+        }
+
+        if rec1.toc > rec2.toc {
+                return Ok(3);
+        }
+
+        if rec1.toc < rec2.toc {
+                return Ok(-3);
+        }
+
+
+        return Ok(0);
+}
+
+/// Folds PSTM sentences from many sources into the freshest record per
+/// satellite, keyed by `satid`.
+#[derive(Debug, Default)]
+pub struct Collator {
+        sats: HashMap<u16, SatRec>,
+}
+
+impl Collator {
+        pub fn new() -> Self {
+                Self { sats: HashMap::new() }
+        }
+
+        /// Parse and fold a single NMEA line into the collated set. Malformed or
+        /// unrecognized lines are reported and skipped rather than returned as an
+        /// error, matching the line-oriented, best-effort nature of ingestion.
+        pub fn ingest_line(&mut self, line: &str, quiet: bool) {
+                let sat = match parse_line(line, quiet) {
+                        Ok(s) => s,
+                        Err(e) => {
+                                if !quiet { println!("Failed processing record: {}", e); }
+                                return;
+                        }
+                };
+
+                if sat.satid == 0 {
+                        return;
+                }
+
+                if !self.sats.contains_key(&sat.satid) {
+                        if !quiet { println!("Inserting {:?}", sat); }
+                        self.sats.insert(sat.satid, sat);
+                } else {
+                        let cmp: i8;
+
+                        {
+                                let old_sat = self.sats.get(&sat.satid).unwrap();
+                                match compare_date(&sat, old_sat) {
+                                        Ok(n) => cmp = n,
+                                        Err(e) => { if !quiet { println!("{}", e); } return; }
+                                }
+                        }
+
+                        if cmp > 0 {
+                                if !quiet { println!("Replacing {:?}", sat); }
+                                self.sats.insert(sat.satid, sat);
+                        } else if !quiet {
+                                println!("Skipping {:?}", sat);
+                        }
+                }
+        }
+
+        /// Read an entire source to a string and fold every line into the
+        /// collated set.
+        pub fn ingest_reader<R: Read>(&mut self, mut reader: R, quiet: bool) -> Result<(), CollateError> {
+                let mut contents = String::new();
+                reader.read_to_string(&mut contents)?;
+
+                if !quiet { println!("in file\n{}", contents); }
+
+                for line in contents.split('\n') {
+                        self.ingest_line(line, quiet);
+                }
+
+                Ok(())
+        }
+
+        /// The collated records, sorted by `satid`.
+        pub fn records(&self) -> Vec<&SatRec> {
+                let mut records: Vec<&SatRec> = self.sats.values().collect();
+                records.sort_by_key(|sat| sat.satid);
+                records
+        }
+}
+
+/// Convenience wrapper that opens each path and folds it into a fresh
+/// [`Collator`].
+pub fn collate_files<P: AsRef<std::path::Path>>(paths: &[P], quiet: bool) -> Result<Collator, CollateError> {
+        let mut collator = Collator::new();
+
+        for path in paths {
+                let f = std::fs::File::open(path)?;
+                collator.ingest_reader(f, quiet)?;
+        }
+
+        Ok(collator)
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        /// Assemble a valid `$PSTMALMANAC` GPS sentence carrying the given
+        /// header fields and payload, with a correct trailing NMEA checksum.
+        fn build_gps_almanac_line(satid: u8, week: u16, toa: u8, rem: &[u8]) -> String {
+                let mut bytes = vec![satid, week as u8, (week >> 8) as u8, toa];
+                bytes.extend_from_slice(rem);
+
+                let body = format!("PSTMALMANAC,{},40,{}", satid, hex::encode(&bytes));
+                let checksum = body.bytes().fold(0u8, |acc, b| acc ^ b);
+
+                format!("${}*{:02x}", body, checksum)
+        }
+
+        #[test]
+        fn parse_line_decodes_gps_almanac() {
+                let rem = [0u8; 27];
+                let line = build_gps_almanac_line(5, 100, 10, &rem);
+
+                let sat = parse_line(&line, true).expect("should parse");
+                assert_eq!(sat.satid, 5);
+                assert_eq!(sat.week, 100);
+                assert_eq!(sat.toa, 10);
+                assert_eq!(sat.rec_type, RecType::Almanac);
+        }
+
+        #[test]
+        fn parse_line_rejects_malformed_line() {
+                assert!(matches!(parse_line("not,a,valid,pstm,line", true), Err(CollateError::MalformedLine)));
+        }
+
+        #[test]
+        fn parse_line_rejects_bad_checksum() {
+                let rem = [0u8; 27];
+                let line = build_gps_almanac_line(5, 100, 10, &rem);
+                let good_checksum = u8::from_str_radix(&line[line.len() - 2..], 16).unwrap();
+                let mut line = line;
+                let len = line.len();
+                line.replace_range(len - 2.., &format!("{:02x}", good_checksum ^ 0xff));
+
+                assert!(matches!(parse_line(&line, true), Err(CollateError::BadChecksum { .. })));
+        }
+
+        #[test]
+        fn collator_keeps_freshest_almanac() {
+                let rem = [0u8; 27];
+                let mut collator = Collator::new();
+
+                collator.ingest_line(&build_gps_almanac_line(5, 100, 10, &rem), true);
+                collator.ingest_line(&build_gps_almanac_line(5, 101, 3, &rem), true);
+                collator.ingest_line(&build_gps_almanac_line(5, 99, 20, &rem), true);
+
+                let records = collator.records();
+                assert_eq!(records.len(), 1);
+                assert_eq!(records[0].week, 101);
+        }
+}